@@ -2,118 +2,78 @@
 //! The arithmetic addition instruction.
 //!
 
+use super::arithmetic::impl_binary_arith;
 use super::*;
-use crate::assembly::operand::{FullOperand, RegisterOperand};
-use crate::error::{BinaryParseError, InstructionReadError};
-use std::collections::HashMap;
-use std::convert::TryFrom;
 
-///
-/// The arithmetic addition instruction.
-///
-#[derive(Debug, Clone, PartialEq)]
-pub struct Add {
-    /// Condition for execution
-    pub condition: ConditionCase,
-    /// Whether we set flags or not
-    pub set_flags_option: SetFlags,
-    /// The first operand.
-    pub source_1: FullOperand,
-    /// The second operand.
-    pub source_2: RegisterOperand,
-    /// The destination operand.
-    pub destination: FullOperand,
+impl_binary_arith! {
+    /// The arithmetic addition instruction.
+    struct Add {
+        opcode: Opcode::Add(AddOpcode::Add),
+        num_destinations: 1,
+    }
 }
 
-impl Add {
-    // Total number of arguments in canonical form
-    pub const NUM_ARGUMENTS: usize = 3;
-
-    #[track_caller]
-    pub fn build_from_parts(
-        mut modifiers: HashSet<&str>,
-        operands: Vec<&str>,
-    ) -> Result<Self, InstructionReadError> {
-        let operands = if let Ok(operands) = parse_canonical_operands_sequence(
-            operands.clone(),
-            &[marker_full_operand(), marker_register_operand()],
-            &[marker_full_operand()],
-        ) {
-            operands
-        } else {
-            // try loading label
-            parse_canonical_operands_sequence(
-                operands,
-                &[OperandType::Label, marker_register_operand()],
-                &[marker_full_operand()],
-            )?
-        };
-
-        let src0 = operands[0].clone();
-        let src1 = operands[1].clone();
-        let dst0 = operands[2].clone();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
 
-        let condition = pick_condition(&mut modifiers)?;
-        let set_flags_option = pick_setting_flags(&mut modifiers)?;
-
-        if !modifiers.is_empty() {
-            return Err(InstructionReadError::UnknownArgument(format!(
-                "Add instruction contains unknown modifiers: {:?}",
-                modifiers
-            )));
-        }
+    fn modifiers(mods: &[&str]) -> HashSet<&str> {
+        mods.iter().copied().collect()
+    }
 
-        let new = Self {
-            condition,
-            source_1: src0,
-            source_2: src1.as_register_operand(1)?,
-            destination: dst0,
-            set_flags_option,
-        };
+    #[test]
+    fn reassembling_a_parsed_instruction_is_stable() {
+        let add = Add::build_from_parts(modifiers(&[]), vec!["r1", "r2", "r3"]).unwrap();
+        let text = add.to_assembly_string();
+        assert_eq!(text, "add r1, r2, r3");
 
-        Ok(new)
+        let operands: Vec<&str> = text.split(&[' ', ','][..]).filter(|s| !s.is_empty()).collect();
+        let reparsed = Add::build_from_parts(modifiers(&[]), operands[1..].to_vec()).unwrap();
+        assert_eq!(add, reparsed);
+        assert_eq!(reparsed.to_assembly_string(), text);
     }
 
-    #[track_caller]
-    pub(crate) fn link<const N: usize, E: VmEncodingMode<N>>(
-        &mut self,
-        function_labels_to_pc: &HashMap<String, usize>,
-        constant_labels_to_offset: &HashMap<String, usize>,
-        globals_to_offsets: &HashMap<String, usize>,
-    ) -> Result<(), AssemblyParseError> {
-        link_operand::<N, E>(
-            &mut self.source_1,
-            function_labels_to_pc,
-            constant_labels_to_offset,
-            globals_to_offsets,
-        )?;
+    #[test]
+    fn swap_modifier_sets_the_swap_flag_and_round_trips() {
+        let add = Add::build_from_parts(modifiers(&["swap"]), vec!["r1", "r2", "r3"]).unwrap();
+        assert!(add.swap_operands);
+        assert!(add.to_assembly_string().contains(".swap"));
+    }
 
-        link_operand::<N, E>(
-            &mut self.destination,
-            function_labels_to_pc,
-            constant_labels_to_offset,
-            globals_to_offsets,
-        )?;
+    #[test]
+    fn small_literal_source_is_encoded_directly_not_pooled() {
+        let add = Add::build_from_parts(modifiers(&[]), vec!["0x1", "r2", "r3"]).unwrap();
+        assert!(add.inline_literal.is_none());
+    }
 
-        Ok(())
+    #[test]
+    fn oversized_literal_source_is_kept_for_the_constant_pool() {
+        let literal = format!("0x{}", "1".repeat(64));
+        let add = Add::build_from_parts(modifiers(&[]), vec![&literal, "r2", "r3"]).unwrap();
+        assert!(add.inline_literal.is_some());
     }
-}
 
-impl<const N: usize, E: VmEncodingMode<N>> TryFrom<Add> for DecodedOpcode<N, E> {
-    type Error = InstructionReadError;
+    #[test]
+    fn indirect_destination_is_parsed_and_round_trips() {
+        let add = Add::build_from_parts(modifiers(&[]), vec!["r1", "r2", "[r3 + 8]"]).unwrap();
+        assert!(add.indirect_destination.is_some());
+        let text = add.to_assembly_string();
+        assert_eq!(text, "add r1, r2, [r3 + 8]");
+    }
 
-    fn try_from(value: Add) -> Result<Self, Self::Error> {
-        let mut new = DecodedOpcode::default();
-        new.variant = OpcodeVariant {
-            opcode: Opcode::Add(AddOpcode::Add),
-            ..OpcodeVariant::default()
-        };
-        set_src0_or_dst0_full_operand(&value.source_1.as_generic_operand(0)?, &mut new, false);
-        set_register_operand(&value.source_2, &mut new, false);
-        set_src0_or_dst0_full_operand(&value.destination.as_generic_operand(2)?, &mut new, true);
-        new.condition = value.condition.0;
-        new.variant.flags[SET_FLAGS_FLAG_IDX] = value.set_flags_option.0;
+    #[test]
+    fn source_2_rejects_indirect_addressing() {
+        let result = Add::build_from_parts(modifiers(&[]), vec!["r1", "[r2 + 4]", "r3"]);
+        assert!(result.is_err());
+    }
 
-        Ok(new)
+    #[test]
+    fn round_trips_through_decoded_opcode_encode_and_decode() {
+        let add = Add::build_from_parts(modifiers(&["swap"]), vec!["r1", "r2", "r3"]).unwrap();
+        let decoded = DecodedOpcode::<8, EncodingModeTesting>::try_from(add.clone()).unwrap();
+        let roundtripped = Add::try_from(decoded).unwrap();
+        assert_eq!(add, roundtripped);
+        assert_eq!(roundtripped.to_assembly_string(), add.to_assembly_string());
     }
 }
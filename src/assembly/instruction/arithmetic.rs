@@ -0,0 +1,503 @@
+//!
+//! Shared code generation for the binary arithmetic instructions
+//! (`Add`, `Sub`, `Mul`, `Div`).
+//!
+//! Every binary arithmetic opcode parses two sources and one or two
+//! destinations, resolves the same set of modifiers, and links/encodes
+//! its operands identically save for the `Opcode` tag and the number of
+//! destination registers it writes. `impl_binary_arith!` emits the
+//! struct plus its `build_from_parts`, `link`, and
+//! `TryFrom<_> for DecodedOpcode` implementations from a single
+//! declaration so new arithmetic opcodes are a one-line addition.
+//!
+
+use super::indirect_operand::{IndirectAddressingMode, IndirectOperand};
+use super::literal_pool::{Literal256, LiteralConstantPool};
+use super::*;
+use crate::assembly::operand::{FullOperand, RegisterOperand};
+use crate::error::{BinaryParseError, InstructionReadError};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Recognizes the `swap` modifier that reverses the roles of `source_1`
+/// and `source_2` before they reach the VM, letting non-commutative ops
+/// like `sub src2, src1` be written without reallocating registers.
+#[track_caller]
+pub(crate) fn pick_swap_operands(modifiers: &mut HashSet<&str>) -> bool {
+    modifiers.remove("swap")
+}
+
+/// Encodes an indirect `[reg + offset]` (optionally stack-relative or
+/// stack-push/pop) destination write: the base register lands in the same
+/// destination register slot a plain register write would use, the offset
+/// is carried as the destination's immediate, and the addressing mode is
+/// recorded via the variant's addressing-mode flags.
+///
+/// Errors if `offset` does not fit into the signed 16-bit immediate field.
+fn set_indirect_destination_operand<const N: usize, E: VmEncodingMode<N>>(
+    base: &RegisterOperand,
+    offset: i32,
+    mode: IndirectAddressingMode,
+    target: &mut DecodedOpcode<N, E>,
+) -> Result<(), InstructionReadError> {
+    let offset = i16::try_from(offset).map_err(|_| {
+        InstructionReadError::UnknownArgument(format!(
+            "indirect operand offset `{}` does not fit into the 16-bit immediate field",
+            offset
+        ))
+    })?;
+
+    set_register_operand(base, target, true);
+    target.imm_1 = offset as u16;
+    target.variant.flags[INDIRECT_DESTINATION_FLAG_IDX] = true;
+    target.variant.flags[STACK_RELATIVE_ADDRESSING_FLAG_IDX] = matches!(
+        mode,
+        IndirectAddressingMode::StackRelative
+            | IndirectAddressingMode::StackPush
+            | IndirectAddressingMode::StackPop
+    );
+    target.variant.flags[STACK_PUSH_FLAG_IDX] = matches!(mode, IndirectAddressingMode::StackPush);
+    target.variant.flags[STACK_POP_FLAG_IDX] = matches!(mode, IndirectAddressingMode::StackPop);
+
+    Ok(())
+}
+
+/// Reconstructs the `(base, offset, mode)` triple written by
+/// [`set_indirect_destination_operand`], or `None` if the destination was
+/// encoded as a plain operand instead.
+fn get_indirect_destination_operand<const N: usize, E: VmEncodingMode<N>>(
+    value: &DecodedOpcode<N, E>,
+) -> Option<(RegisterOperand, i32, IndirectAddressingMode)> {
+    if !value.variant.flags[INDIRECT_DESTINATION_FLAG_IDX] {
+        return None;
+    }
+
+    let base = get_register_operand(value, true);
+    let offset = (value.imm_1 as i16) as i32;
+    let mode = if value.variant.flags[STACK_PUSH_FLAG_IDX] {
+        IndirectAddressingMode::StackPush
+    } else if value.variant.flags[STACK_POP_FLAG_IDX] {
+        IndirectAddressingMode::StackPop
+    } else if value.variant.flags[STACK_RELATIVE_ADDRESSING_FLAG_IDX] {
+        IndirectAddressingMode::StackRelative
+    } else {
+        IndirectAddressingMode::RegisterRelative
+    };
+
+    Some((base, offset, mode))
+}
+
+/// Declares a binary arithmetic instruction struct together with its
+/// `build_from_parts`, `link`, and `TryFrom<_> for DecodedOpcode` implementations.
+///
+/// `num_destinations` is `1` for single-output arithmetic (`Add`, `Sub`) or `2`
+/// for the dual-output ops (`Mul` writes high/low, `Div` writes quotient/remainder).
+macro_rules! impl_binary_arith {
+    (
+        $(#[$doc:meta])*
+        struct $name:ident {
+            opcode: $opcode:expr,
+            num_destinations: $num_destinations:expr,
+        }
+    ) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name {
+            /// Condition for execution
+            pub condition: ConditionCase,
+            /// Whether we set flags or not
+            pub set_flags_option: SetFlags,
+            /// The first operand.
+            pub source_1: FullOperand,
+            /// The second operand.
+            pub source_2: RegisterOperand,
+            /// The (first) destination operand.
+            pub destination: FullOperand,
+            /// The second destination operand, present only for dual-output ops.
+            pub destination_2: Option<FullOperand>,
+            /// Whether `source_1` and `source_2` should be swapped by the VM
+            /// before the operation is applied.
+            pub swap_operands: bool,
+            /// Set when `source_1` was written as an inline 256-bit literal
+            /// rather than a register or a pre-declared constant label; the
+            /// literal is interned into the constant pool during [`Self::link`].
+            pub inline_literal: Option<Literal256>,
+            /// Set when `destination` was written as an indirect
+            /// `[reg + offset]` (optionally stack-relative/push/pop) address
+            /// rather than a plain register or label.
+            pub indirect_destination: Option<(RegisterOperand, i32, IndirectAddressingMode)>,
+        }
+
+        impl $name {
+            // Total number of arguments in canonical form
+            pub const NUM_ARGUMENTS: usize = 2 + $num_destinations;
+
+            #[track_caller]
+            pub fn build_from_parts(
+                mut modifiers: HashSet<&str>,
+                operands: Vec<&str>,
+            ) -> Result<Self, InstructionReadError> {
+                if operands.len() != Self::NUM_ARGUMENTS {
+                    return Err(InstructionReadError::UnknownArgument(format!(
+                        "{} instruction expects {} operands, got {}: {:?}",
+                        stringify!($name),
+                        Self::NUM_ARGUMENTS,
+                        operands.len(),
+                        operands
+                    )));
+                }
+
+                if IndirectOperand::parse(operands[1])?.is_some() {
+                    return Err(InstructionReadError::UnknownArgument(format!(
+                        "{} instruction source operands must be registers, not indirect addresses: {:?}",
+                        stringify!($name),
+                        operands[1]
+                    )));
+                }
+
+                let mut destination_markers = Vec::with_capacity($num_destinations);
+                for _ in 0..$num_destinations {
+                    destination_markers.push(marker_full_operand());
+                }
+
+                let mut operands = operands;
+                let indirect_destination = match IndirectOperand::parse(operands[2])? {
+                    Some(indirect) => {
+                        let base = parse_canonical_operands_sequence(
+                            vec![indirect.base_register_text],
+                            &[marker_register_operand()],
+                            &[],
+                        )?
+                        .remove(0)
+                        .as_register_operand(2)?;
+                        operands[2] = indirect.base_register_text;
+                        Some((base, indirect.offset, indirect.mode))
+                    }
+                    None => None,
+                };
+
+                let mut inline_literal = None;
+
+                let operands = if let Ok(operands) = parse_canonical_operands_sequence(
+                    operands.clone(),
+                    &[marker_full_operand(), marker_register_operand()],
+                    &destination_markers,
+                ) {
+                    operands
+                } else if let Ok(operands) = parse_canonical_operands_sequence(
+                    operands.clone(),
+                    &[OperandType::Label, marker_register_operand()],
+                    &destination_markers,
+                ) {
+                    // try loading label
+                    operands
+                } else {
+                    // try an inline 256-bit literal
+                    let literal = Literal256::parse_hex(operands[0])?;
+                    // Re-parsed with `operands[0]` swapped for a manufactured
+                    // token (a plain immediate or a synthetic label). That
+                    // token only needs to outlive this call, so it's built as
+                    // a fresh, independently-lived `Vec<&str>` borrowing the
+                    // local `String` rather than mutated in place into
+                    // `operands` (which would force a `'static` leak to
+                    // satisfy its longer-lived element type).
+                    if literal.fits_in_immediate() {
+                        // small enough to encode directly: reparse as a plain
+                        // immediate, no constant pool involved
+                        let immediate = format!("{}", literal.low);
+                        let operands: Vec<&str> = std::iter::once(immediate.as_str())
+                            .chain(operands[1..].iter().copied())
+                            .collect();
+                        parse_canonical_operands_sequence(
+                            operands,
+                            &[marker_full_operand(), marker_register_operand()],
+                            &destination_markers,
+                        )?
+                    } else {
+                        // too wide for an immediate: intern into the constant
+                        // pool under a synthetic label, resolved at link time
+                        inline_literal = Some(literal);
+                        let synthetic_label = literal.synthetic_label();
+                        let operands: Vec<&str> = std::iter::once(synthetic_label.as_str())
+                            .chain(operands[1..].iter().copied())
+                            .collect();
+                        parse_canonical_operands_sequence(
+                            operands,
+                            &[OperandType::Label, marker_register_operand()],
+                            &destination_markers,
+                        )?
+                    }
+                };
+
+                let src0 = operands[0].clone();
+                let src1 = operands[1].clone();
+                let dst0 = operands[2].clone();
+                let dst1 = if $num_destinations > 1 {
+                    Some(operands[3].clone())
+                } else {
+                    None
+                };
+
+                let condition = pick_condition(&mut modifiers)?;
+                let set_flags_option = pick_setting_flags(&mut modifiers)?;
+                let swap_operands = pick_swap_operands(&mut modifiers);
+
+                if !modifiers.is_empty() {
+                    return Err(InstructionReadError::UnknownArgument(format!(
+                        "{} instruction contains unknown modifiers: {:?}",
+                        stringify!($name),
+                        modifiers
+                    )));
+                }
+
+                Ok(Self {
+                    condition,
+                    source_1: src0,
+                    source_2: src1.as_register_operand(1)?,
+                    destination: dst0,
+                    destination_2: dst1,
+                    set_flags_option,
+                    swap_operands,
+                    inline_literal,
+                    indirect_destination,
+                })
+            }
+
+            #[track_caller]
+            pub(crate) fn link<const N: usize, E: VmEncodingMode<N>>(
+                &mut self,
+                function_labels_to_pc: &HashMap<String, usize>,
+                constant_labels_to_offset: &HashMap<String, usize>,
+                globals_to_offsets: &HashMap<String, usize>,
+                literal_pool: &mut LiteralConstantPool,
+            ) -> Result<(), AssemblyParseError> {
+                match self.inline_literal {
+                    // synthesized during parsing only for literals too wide to
+                    // fit as an immediate (see `build_from_parts`); anything
+                    // that fits was already encoded as a plain immediate and
+                    // needs no constant-pool resolution here
+                    Some(literal) => {
+                        // `source_1` was rewritten to exactly this synthetic
+                        // label during parsing, so it's the only key the
+                        // lookup needs: a single-entry map avoids cloning the
+                        // full (and potentially large) declared-constants map
+                        let offset = literal_pool.intern(literal, constant_labels_to_offset.len());
+                        let synthetic_constant_labels_to_offset =
+                            HashMap::from([(literal.synthetic_label(), offset)]);
+
+                        link_operand::<N, E>(
+                            &mut self.source_1,
+                            function_labels_to_pc,
+                            &synthetic_constant_labels_to_offset,
+                            globals_to_offsets,
+                        )?;
+                    }
+                    None => {
+                        link_operand::<N, E>(
+                            &mut self.source_1,
+                            function_labels_to_pc,
+                            constant_labels_to_offset,
+                            globals_to_offsets,
+                        )?;
+                    }
+                }
+
+                link_operand::<N, E>(
+                    &mut self.destination,
+                    function_labels_to_pc,
+                    constant_labels_to_offset,
+                    globals_to_offsets,
+                )?;
+
+                if let Some(destination_2) = self.destination_2.as_mut() {
+                    link_operand::<N, E>(
+                        destination_2,
+                        function_labels_to_pc,
+                        constant_labels_to_offset,
+                        globals_to_offsets,
+                    )?;
+                }
+
+                Ok(())
+            }
+        }
+
+        impl<const N: usize, E: VmEncodingMode<N>> TryFrom<$name> for DecodedOpcode<N, E> {
+            type Error = InstructionReadError;
+
+            fn try_from(value: $name) -> Result<Self, Self::Error> {
+                let mut new = DecodedOpcode::default();
+                new.variant = OpcodeVariant {
+                    opcode: $opcode,
+                    ..OpcodeVariant::default()
+                };
+                set_src0_or_dst0_full_operand(
+                    &value.source_1.as_generic_operand(0)?,
+                    &mut new,
+                    false,
+                );
+                set_register_operand(&value.source_2, &mut new, false);
+                match &value.indirect_destination {
+                    Some((base, offset, mode)) => {
+                        set_indirect_destination_operand(base, *offset, *mode, &mut new)?;
+                    }
+                    None => {
+                        set_src0_or_dst0_full_operand(
+                            &value.destination.as_generic_operand(2)?,
+                            &mut new,
+                            true,
+                        );
+                    }
+                }
+                if let Some(destination_2) = value.destination_2 {
+                    // distinct from the primary destination's slot: reuses
+                    // `set_src0_or_dst0_full_operand` here would overwrite the
+                    // bits just written above, since decode reads this second
+                    // slot back out via the separate `get_dst1_full_operand`
+                    set_dst1_full_operand(&destination_2.as_generic_operand(3)?, &mut new);
+                }
+                new.condition = value.condition.0;
+                new.variant.flags[SET_FLAGS_FLAG_IDX] = value.set_flags_option.0;
+                new.variant.flags[SWAP_OPERANDS_FLAG_IDX] = value.swap_operands;
+
+                Ok(new)
+            }
+        }
+
+        impl<const N: usize, E: VmEncodingMode<N>> TryFrom<DecodedOpcode<N, E>> for $name {
+            type Error = InstructionReadError;
+
+            fn try_from(value: DecodedOpcode<N, E>) -> Result<Self, Self::Error> {
+                let source_1 =
+                    FullOperand::try_from(get_src0_or_dst0_full_operand(&value, false))?;
+                let source_2 = get_register_operand(&value, false);
+                let indirect_destination = get_indirect_destination_operand(&value);
+                // `destination` mirrors the register slot regardless of
+                // addressing mode; it's only read when `indirect_destination`
+                // is `None` (see `Display` and the encode direction above)
+                let destination =
+                    FullOperand::try_from(get_src0_or_dst0_full_operand(&value, true))?;
+                let destination_2 = if $num_destinations > 1 {
+                    Some(FullOperand::try_from(get_dst1_full_operand(&value))?)
+                } else {
+                    None
+                };
+
+                Ok(Self {
+                    condition: ConditionCase(value.condition),
+                    set_flags_option: SetFlags(value.variant.flags[SET_FLAGS_FLAG_IDX]),
+                    swap_operands: value.variant.flags[SWAP_OPERANDS_FLAG_IDX],
+                    source_1,
+                    source_2,
+                    destination,
+                    destination_2,
+                    inline_literal: None,
+                    indirect_destination,
+                })
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", stringify!($name).to_lowercase())?;
+                write!(f, "{}", self.condition)?;
+                if self.set_flags_option.0 {
+                    write!(f, ".s")?;
+                }
+                if self.swap_operands {
+                    write!(f, ".swap")?;
+                }
+                write!(f, " {}, {}, ", self.source_1, self.source_2)?;
+                match &self.indirect_destination {
+                    Some((base, offset, mode)) => {
+                        let prefix = match mode {
+                            IndirectAddressingMode::RegisterRelative => "",
+                            IndirectAddressingMode::StackRelative => "stack",
+                            IndirectAddressingMode::StackPush => "stack+=",
+                            IndirectAddressingMode::StackPop => "stack-=",
+                        };
+                        let sign = if *offset < 0 { '-' } else { '+' };
+                        write!(f, "{}[{} {} {}]", prefix, base, sign, offset.unsigned_abs())?
+                    }
+                    None => write!(f, "{}", self.destination)?,
+                }
+                if let Some(destination_2) = self.destination_2.as_ref() {
+                    write!(f, ", {}", destination_2)?;
+                }
+
+                Ok(())
+            }
+        }
+
+        impl $name {
+            /// Reconstructs the canonical textual assembly for this instruction,
+            /// matching what [`Self::build_from_parts`] accepts as input.
+            pub fn to_assembly_string(&self) -> String {
+                self.to_string()
+            }
+        }
+    };
+}
+
+pub(crate) use impl_binary_arith;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal256_dedups_equal_values_regardless_of_leading_zeros() {
+        let a = Literal256::parse_hex("0x01").unwrap();
+        let b = Literal256::parse_hex("0x1").unwrap();
+        assert_eq!(a, b);
+        assert!(a.fits_in_immediate());
+
+        let mut pool = LiteralConstantPool::default();
+        let offset_a = pool.intern(a, 0);
+        let offset_b = pool.intern(b, 0);
+        assert_eq!(offset_a, offset_b);
+        assert_eq!(pool.values().len(), 1);
+    }
+
+    #[test]
+    fn literal256_wider_than_immediate_is_not_fits_in_immediate() {
+        let wide = Literal256::parse_hex(&format!("0x1{}", "0".repeat(32))).unwrap();
+        assert!(!wide.fits_in_immediate());
+    }
+
+    #[test]
+    fn literal256_rejects_values_wider_than_256_bits() {
+        let too_wide = format!("0x{}", "f".repeat(65));
+        assert!(Literal256::parse_hex(&too_wide).is_err());
+    }
+
+    #[test]
+    fn pick_swap_operands_consumes_only_the_swap_token() {
+        let mut modifiers: HashSet<&str> = ["swap", "eq"].into_iter().collect();
+        assert!(pick_swap_operands(&mut modifiers));
+        assert_eq!(modifiers, ["eq"].into_iter().collect());
+
+        let mut modifiers: HashSet<&str> = ["eq"].into_iter().collect();
+        assert!(!pick_swap_operands(&mut modifiers));
+    }
+
+    #[test]
+    fn indirect_operand_parses_all_addressing_forms() {
+        let plain = IndirectOperand::parse("[r3 + 16]").unwrap().unwrap();
+        assert_eq!(plain.base_register_text, "r3");
+        assert_eq!(plain.offset, 16);
+        assert_eq!(plain.mode, IndirectAddressingMode::RegisterRelative);
+
+        let stack = IndirectOperand::parse("stack[r1 - 4]").unwrap().unwrap();
+        assert_eq!(stack.offset, -4);
+        assert_eq!(stack.mode, IndirectAddressingMode::StackRelative);
+
+        let push = IndirectOperand::parse("stack+=[r1]").unwrap().unwrap();
+        assert_eq!(push.mode, IndirectAddressingMode::StackPush);
+
+        let pop = IndirectOperand::parse("stack-=[r1]").unwrap().unwrap();
+        assert_eq!(pop.mode, IndirectAddressingMode::StackPop);
+
+        assert!(IndirectOperand::parse("r1").unwrap().is_none());
+    }
+}
@@ -0,0 +1,39 @@
+//!
+//! The arithmetic division instruction.
+//!
+//! Division writes two destination registers: the quotient in `destination`,
+//! and the remainder in `destination_2`.
+//!
+
+use super::arithmetic::impl_binary_arith;
+use super::*;
+
+impl_binary_arith! {
+    /// The arithmetic division instruction.
+    struct Div {
+        opcode: Opcode::Div(DivOpcode::Div),
+        num_destinations: 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn parses_both_quotient_and_remainder_destination_registers() {
+        let div = Div::build_from_parts(HashSet::new(), vec!["r1", "r2", "r3", "r4"]).unwrap();
+        assert!(div.destination_2.is_some());
+        assert_eq!(div.to_assembly_string(), "div r1, r2, r3, r4");
+    }
+
+    #[test]
+    fn round_trips_through_decoded_opcode_encode_and_decode() {
+        let div = Div::build_from_parts(HashSet::new(), vec!["r1", "r2", "r3", "r4"]).unwrap();
+        let decoded = DecodedOpcode::<8, EncodingModeTesting>::try_from(div.clone()).unwrap();
+        let roundtripped = Div::try_from(decoded).unwrap();
+        assert_eq!(div, roundtripped);
+        assert_eq!(roundtripped.destination_2, div.destination_2);
+    }
+}
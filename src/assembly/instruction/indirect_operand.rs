@@ -0,0 +1,70 @@
+//!
+//! Indirect (memory/stack) addressing for destination operands: writing a
+//! result to `[reg + offset]` instead of directly into a register, with
+//! optional stack-relative and stack-push/pop forms.
+//!
+
+use crate::error::InstructionReadError;
+
+/// How the base register and offset of an [`IndirectOperand`] are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndirectAddressingMode {
+    /// Plain `[reg + offset]` addressing.
+    RegisterRelative,
+    /// `stack[reg + offset]`: relative to the current stack frame, without
+    /// moving the stack pointer.
+    StackRelative,
+    /// `stack+=[reg + offset]`: advances the stack pointer, then writes.
+    StackPush,
+    /// `stack-=[reg + offset]`: writes, then advances the stack pointer.
+    StackPop,
+}
+
+/// A register base plus a signed immediate offset, e.g. `[r3 - 16]` or
+/// `stack+=[r3]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndirectOperand<'a> {
+    pub base_register_text: &'a str,
+    pub offset: i32,
+    pub mode: IndirectAddressingMode,
+}
+
+impl<'a> IndirectOperand<'a> {
+    /// Recognizes `token` as one of the supported indirect destination
+    /// forms. Returns `None` (not an error) if `token` uses none of them,
+    /// so callers can fall back to treating it as a plain operand.
+    pub fn parse(token: &'a str) -> Result<Option<Self>, InstructionReadError> {
+        let (mode, rest) = if let Some(rest) = token.strip_prefix("stack+=") {
+            (IndirectAddressingMode::StackPush, rest)
+        } else if let Some(rest) = token.strip_prefix("stack-=") {
+            (IndirectAddressingMode::StackPop, rest)
+        } else if let Some(rest) = token.strip_prefix("stack") {
+            (IndirectAddressingMode::StackRelative, rest)
+        } else {
+            (IndirectAddressingMode::RegisterRelative, token)
+        };
+
+        let inner = match rest.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+
+        let (base_register_text, offset_text) = match inner.find(['+', '-']) {
+            Some(idx) => (inner[..idx].trim(), inner[idx..].trim()),
+            None => (inner.trim(), "+0"),
+        };
+
+        let offset = offset_text.replace(' ', "").parse::<i32>().map_err(|_| {
+            InstructionReadError::UnknownArgument(format!(
+                "invalid indirect operand offset `{}` in `{}`",
+                offset_text, token
+            ))
+        })?;
+
+        Ok(Some(Self {
+            base_register_text,
+            offset,
+            mode,
+        }))
+    }
+}
@@ -0,0 +1,99 @@
+//!
+//! Inline 256-bit literal constants referenced directly from an
+//! instruction's source operand (e.g. `add 0x1234...abcd, r1, r2`) instead
+//! of a pre-declared `const` label.
+//!
+//! Literals are decomposed into the high/low 128-bit limbs the way the
+//! circuit layer represents words, and deduplicated by value so the same
+//! literal written twice only occupies one constant-pool slot.
+//!
+
+use crate::error::InstructionReadError;
+
+/// A 256-bit value split into its high and low 128-bit limbs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Literal256 {
+    pub high: u128,
+    pub low: u128,
+}
+
+impl Literal256 {
+    /// Parses a `0x`-prefixed hexadecimal literal of up to 64 hex digits
+    /// (256 bits) into its high/low limbs.
+    pub fn parse_hex(literal: &str) -> Result<Self, InstructionReadError> {
+        let digits = literal
+            .strip_prefix("0x")
+            .or_else(|| literal.strip_prefix("0X"))
+            .ok_or_else(|| {
+                InstructionReadError::UnknownArgument(format!(
+                    "`{}` is not a hexadecimal literal",
+                    literal
+                ))
+            })?;
+
+        if digits.is_empty() || digits.len() > 64 || !digits.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(InstructionReadError::UnknownArgument(format!(
+                "literal `{}` is not a valid 256-bit hexadecimal constant",
+                literal
+            )));
+        }
+
+        let padded = format!("{:0>64}", digits);
+        let (high_digits, low_digits) = padded.split_at(32);
+        let high = u128::from_str_radix(high_digits, 16).map_err(|_| {
+            InstructionReadError::UnknownArgument(format!("invalid literal `{}`", literal))
+        })?;
+        let low = u128::from_str_radix(low_digits, 16).map_err(|_| {
+            InstructionReadError::UnknownArgument(format!("invalid literal `{}`", literal))
+        })?;
+
+        Ok(Self { high, low })
+    }
+
+    /// Whether this literal is small enough to be encoded directly as an
+    /// immediate, in which case it should not be pooled at all.
+    pub fn fits_in_immediate(&self) -> bool {
+        self.high == 0 && self.low <= Self::MAX_IMMEDIATE as u128
+    }
+
+    /// Largest value of the immediate field that can be encoded directly in
+    /// the opcode without going through the constant pool.
+    const MAX_IMMEDIATE: u16 = u16::MAX;
+
+    /// A deterministic, reserved constant label synthesized for this
+    /// literal so it can travel through the existing label-based
+    /// `FullOperand`/`link_operand` machinery unchanged.
+    pub fn synthetic_label(&self) -> String {
+        format!("__inline_literal_{:032x}{:032x}", self.high, self.low)
+    }
+}
+
+/// Deduplicating pool of inline literal constants, assigning each distinct
+/// value an offset following the assembly's declared constants.
+#[derive(Debug, Default)]
+pub struct LiteralConstantPool {
+    values: Vec<Literal256>,
+    offsets: std::collections::HashMap<Literal256, usize>,
+}
+
+impl LiteralConstantPool {
+    /// Interns `literal`, returning its constant-pool offset relative to
+    /// `base_offset` (the number of already-declared constants). Identical
+    /// literals reuse the same offset.
+    pub fn intern(&mut self, literal: Literal256, base_offset: usize) -> usize {
+        if let Some(offset) = self.offsets.get(&literal) {
+            return *offset;
+        }
+
+        let offset = base_offset + self.values.len();
+        self.values.push(literal);
+        self.offsets.insert(literal, offset);
+        offset
+    }
+
+    /// The interned literals, in the order they were first seen.
+    pub fn values(&self) -> &[Literal256] {
+        &self.values
+    }
+}
@@ -0,0 +1,17 @@
+//!
+//! Instructions supported by the assembly.
+//!
+
+pub mod arithmetic;
+pub mod indirect_operand;
+pub mod literal_pool;
+
+pub mod add;
+pub mod div;
+pub mod mul;
+pub mod sub;
+
+pub use add::Add;
+pub use div::Div;
+pub use mul::Mul;
+pub use sub::Sub;
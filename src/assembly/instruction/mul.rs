@@ -0,0 +1,39 @@
+//!
+//! The arithmetic multiplication instruction.
+//!
+//! Multiplication writes two destination registers: the low 256 bits of the
+//! product in `destination`, and the high 256 bits in `destination_2`.
+//!
+
+use super::arithmetic::impl_binary_arith;
+use super::*;
+
+impl_binary_arith! {
+    /// The arithmetic multiplication instruction.
+    struct Mul {
+        opcode: Opcode::Mul(MulOpcode::Mul),
+        num_destinations: 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn parses_both_low_and_high_destination_registers() {
+        let mul = Mul::build_from_parts(HashSet::new(), vec!["r1", "r2", "r3", "r4"]).unwrap();
+        assert!(mul.destination_2.is_some());
+        assert_eq!(mul.to_assembly_string(), "mul r1, r2, r3, r4");
+    }
+
+    #[test]
+    fn round_trips_through_decoded_opcode_encode_and_decode() {
+        let mul = Mul::build_from_parts(HashSet::new(), vec!["r1", "r2", "r3", "r4"]).unwrap();
+        let decoded = DecodedOpcode::<8, EncodingModeTesting>::try_from(mul.clone()).unwrap();
+        let roundtripped = Mul::try_from(decoded).unwrap();
+        assert_eq!(mul, roundtripped);
+        assert_eq!(roundtripped.destination_2, mul.destination_2);
+    }
+}
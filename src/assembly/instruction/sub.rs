@@ -0,0 +1,28 @@
+//!
+//! The arithmetic subtraction instruction.
+//!
+
+use super::arithmetic::impl_binary_arith;
+use super::*;
+
+impl_binary_arith! {
+    /// The arithmetic subtraction instruction.
+    struct Sub {
+        opcode: Opcode::Sub(SubOpcode::Sub),
+        num_destinations: 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn swap_lets_reversed_operand_order_be_written_without_reallocating_registers() {
+        let modifiers: HashSet<&str> = ["swap"].into_iter().collect();
+        let sub = Sub::build_from_parts(modifiers, vec!["r1", "r2", "r3"]).unwrap();
+        assert!(sub.swap_operands);
+        assert_eq!(sub.to_assembly_string(), "sub.swap r1, r2, r3");
+    }
+}